@@ -0,0 +1,139 @@
+#![no_std]
+//! The path tracer. `main_v` draws a full-screen triangle and `main_f` shoots
+//! one primary ray per pixel from the camera basis in `Consts`, intersecting the
+//! spheres uploaded into the storage buffers at group 0.
+
+use spirv_std::spirv;
+use spirv_std::glam::{vec2, vec3, Vec2, Vec3, Vec4, Vec4Swizzles};
+use spirv_std::{Image, Sampler};
+use shared::Consts;
+
+// a hash good enough to decorrelate the per-pixel jitter each frame.
+fn hash(mut x: u32) -> f32 {
+  x ^= x >> 16;
+  x = x.wrapping_mul(0x7feb352d);
+  x ^= x >> 15;
+  x = x.wrapping_mul(0x846ca68b);
+  x ^= x >> 16;
+  (x & 0xffffff) as f32 / 0x1000000 as f32
+}
+
+// std430 mirrors of the host `Scene` buffers.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct Sphere {
+  pub center: Vec3,
+  pub radius: f32,
+  pub material: u32,
+  pub _pad: [u32; 3],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct Material {
+  pub albedo: Vec3,
+  pub emissive: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct Counts {
+  pub spheres: u32,
+  pub materials: u32,
+  pub _pad: [u32; 2],
+}
+
+#[spirv(vertex)]
+pub fn main_v(#[spirv(vertex_index)] vert: u32, #[spirv(position)] pos: &mut Vec4) {
+  // one oversized triangle covering the viewport.
+  let uv = vec2(((vert << 1) & 2) as f32, (vert & 2) as f32);
+  *pos = Vec4::new(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+}
+
+// ray/sphere intersection, returns the distance to the near intersection, or
+// -1.0 when that lies behind the origin; the far root is not tested, so a ray
+// starting inside the sphere reports no hit.
+fn hit(origin: Vec3, dir: Vec3, s: &Sphere) -> f32 {
+  let oc = origin - s.center;
+  let b = oc.dot(dir);
+  let c = oc.dot(oc) - s.radius * s.radius;
+  let disc = b * b - c;
+  if disc < 0.0 {
+    -1.0
+  } else {
+    let t = -b - disc.sqrt();
+    if t > 1e-3 {
+      t
+    } else {
+      -1.0
+    }
+  }
+}
+
+type Tex = Image!(2D, type = f32, sampled);
+
+#[spirv(fragment)]
+pub fn main_f(
+  #[spirv(frag_coord)] frag: Vec4,
+  #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] spheres: &[Sphere],
+  #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] materials: &[Material],
+  #[spirv(uniform, descriptor_set = 0, binding = 2)] counts: &Counts,
+  #[spirv(descriptor_set = 1, binding = 0)] prev: &Tex,
+  #[spirv(descriptor_set = 1, binding = 1)] sampler: &Sampler,
+  #[spirv(push_constant)] consts: &Consts,
+  out: &mut Vec4,
+) {
+  // jitter the ray within the pixel for anti-aliasing; the seed varies per frame.
+  let pixel = (frag.x as u32) ^ (frag.y as u32).wrapping_mul(0x9e3779b9);
+  let jitter = vec2(hash(pixel ^ consts.seed), hash(pixel.wrapping_add(consts.seed).wrapping_add(1)));
+  let ndc = ((frag.xy() + jitter - Vec2::splat(0.5)) / consts.screen_size) * 2.0 - Vec2::ONE;
+  let aspect = consts.screen_size.x / consts.screen_size.y;
+  let t = (consts.fov * 0.5).tan();
+  let dir = (consts.cam_forward + consts.cam_right * ndc.x * t * aspect - consts.cam_up * ndc.y * t).normalize();
+
+  // nearest sphere.
+  let mut best = f32::MAX;
+  let mut mat = 0u32;
+  let mut normal = Vec3::ZERO;
+  let mut i = 0u32;
+  while i < counts.spheres {
+    let s = spheres[i as usize];
+    let d = hit(consts.cam_origin, dir, &s);
+    if d > 0.0 && d < best {
+      best = d;
+      mat = s.material;
+      normal = (consts.cam_origin + dir * d - s.center).normalize();
+    }
+    i += 1;
+  }
+
+  let sample = if best < f32::MAX {
+    let m = materials[mat as usize];
+    let light = normal.dot(vec3(0.4, 0.8, 0.3).normalize()).max(0.0);
+    m.albedo * (0.1 + light) + m.albedo * m.emissive
+  } else {
+    vec3(0.02, 0.02, 0.03)
+  };
+
+  // blend the new sample into the running average. `frame` is 1-based so the
+  // first sample after a reset fully replaces the (cleared) history.
+  let uv = frag.xy() / consts.screen_size;
+  let history: Vec4 = prev.sample(*sampler, uv);
+  let blended = history.truncate().lerp(sample, 1.0 / consts.frame as f32);
+  *out = blended.extend(1.0);
+}
+
+#[spirv(fragment)]
+pub fn main_blit(
+  #[spirv(frag_coord)] frag: Vec4,
+  #[spirv(descriptor_set = 0, binding = 0)] accum: &Tex,
+  out: &mut Vec4,
+) {
+  // the blit pipeline carries no push constants, so fetch by texel coordinate
+  // rather than sampling with a uv derived from the screen size.
+  let hdr: Vec4 = accum.fetch(frag.xy().as_uvec2());
+  // reinhard tonemap + gamma.
+  let c = hdr.truncate();
+  let mapped = (c / (c + Vec3::ONE)).powf(1.0 / 2.2);
+  *out = mapped.extend(1.0);
+}
@@ -0,0 +1,31 @@
+#![no_std]
+//! Types shared between the host and the gpu shaders.
+
+use glam::{Vec2, Vec3};
+
+// the ui vertex: pixel position, atlas uv and a flat colour.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct Vertex {
+  pub pos: Vec2,
+  pub uv: Vec2,
+  pub col: Vec3,
+}
+
+// pushed to the fragment stage every frame. the camera basis lets the
+// raytracer build primary rays without any baked constants.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct Consts {
+  pub screen_size: Vec2,
+  pub cam_origin: Vec3,
+  pub cam_forward: Vec3,
+  pub cam_right: Vec3,
+  pub cam_up: Vec3,
+  pub fov: f32,
+  // 1-based sample count for the `1.0/frame` running average, and a per-frame
+  // seed for jittering rays. both reset when the camera moves or the window
+  // resizes so motion clears the accumulated history.
+  pub frame: u32,
+  pub seed: u32,
+}
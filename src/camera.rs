@@ -0,0 +1,135 @@
+use std::f32::consts::PI;
+use glam::{Vec2, Vec3};
+use winit::keyboard::KeyCode;
+
+const PITCH_LIMIT: f32 = 89.0 / 180.0 * PI;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Mode {
+  Orbit,
+  Fly,
+}
+
+pub struct Camera {
+  pub mode: Mode,
+  pub focus: Vec3,
+  pub radius: f32,
+  pub yaw: f32,
+  pub pitch: f32,
+  pub fov: f32,
+  pub eye: Vec3,
+  pub forward: Vec3,
+  pub right: Vec3,
+  pub up: Vec3,
+  held: [bool; 6],
+  dragging: bool,
+  last_cursor: Vec2,
+  pub dirty: bool,
+}
+
+impl Camera {
+  pub fn new() -> Self {
+    let mut c = Self {
+      mode: Mode::Orbit,
+      focus: Vec3::ZERO,
+      radius: 4.0,
+      yaw: 0.0,
+      pitch: 0.0,
+      fov: 60.0 / 180.0 * PI,
+      eye: Vec3::ZERO,
+      forward: Vec3::NEG_Z,
+      right: Vec3::X,
+      up: Vec3::Y,
+      held: [false; 6],
+      dragging: false,
+      last_cursor: Vec2::ZERO,
+      dirty: true,
+    };
+    c.update(0.0);
+    c
+  }
+
+  // left-button drag rotates the view.
+  pub fn drag(&mut self, pressed: bool, cursor: Vec2) {
+    self.dragging = pressed;
+    self.last_cursor = cursor;
+  }
+
+  pub fn cursor(&mut self, cursor: Vec2) {
+    if self.dragging {
+      let delta = cursor - self.last_cursor;
+      self.yaw -= delta.x * 0.005;
+      self.pitch = (self.pitch - delta.y * 0.005).clamp(-PITCH_LIMIT, PITCH_LIMIT);
+      self.dirty = true;
+    }
+    self.last_cursor = cursor;
+  }
+
+  pub fn scroll(&mut self, y: f32) {
+    self.radius = (self.radius * (1.0 - y * 0.1)).max(0.1);
+    self.dirty = true;
+  }
+
+  pub fn key(&mut self, code: KeyCode, pressed: bool) {
+    let i = match code {
+      KeyCode::KeyW => 0,
+      KeyCode::KeyS => 1,
+      KeyCode::KeyA => 2,
+      KeyCode::KeyD => 3,
+      KeyCode::Space => 4,
+      KeyCode::ShiftLeft => 5,
+      KeyCode::Tab if pressed => {
+        self.mode = match self.mode {
+          Mode::Orbit => Mode::Fly,
+          Mode::Fly => Mode::Orbit,
+        };
+        self.dirty = true;
+        return;
+      }
+      _ => return,
+    };
+    self.held[i] = pressed;
+  }
+
+  pub fn update(&mut self, dt: f32) {
+    if self.mode == Mode::Fly {
+      let mut mv = Vec3::ZERO;
+      if self.held[0] {
+        mv += self.forward;
+      }
+      if self.held[1] {
+        mv -= self.forward;
+      }
+      if self.held[2] {
+        mv -= self.right;
+      }
+      if self.held[3] {
+        mv += self.right;
+      }
+      if self.held[4] {
+        mv += Vec3::Y;
+      }
+      if self.held[5] {
+        mv -= Vec3::Y;
+      }
+      if mv != Vec3::ZERO {
+        self.focus += mv.normalize() * dt * 3.0;
+        self.dirty = true;
+      }
+    }
+
+    let (sy, cy) = self.yaw.sin_cos();
+    let (sp, cp) = self.pitch.sin_cos();
+    let offset = Vec3::new(cp * cy, sp, cp * sy);
+    self.eye = match self.mode {
+      Mode::Orbit => self.focus + self.radius * offset,
+      Mode::Fly => self.focus,
+    };
+    self.forward = match self.mode {
+      Mode::Orbit => -offset,
+      Mode::Fly => offset,
+    };
+    self.right = self.forward.cross(Vec3::Y).normalize();
+    self.up = self.right.cross(self.forward);
+  }
+}
@@ -1,19 +1,77 @@
+mod camera;
+mod scene;
 mod ui;
 
+use std::time::Instant;
 use std::{mem, slice};
 use winit::window::WindowBuilder;
 use winit::event_loop::{EventLoop, ControlFlow};
-use winit::event::{Event, WindowEvent, MouseButton, ElementState};
+use winit::event::{Event, WindowEvent, MouseButton, MouseScrollDelta, ElementState};
+use winit::keyboard::PhysicalKey;
 use winit::dpi::PhysicalSize;
 use wgpu::util::DeviceExt;
 use log::LevelFilter;
 use glam::Vec2;
 use shared::{Vertex, Consts};
+use camera::Camera;
+use scene::Scene;
 use ui::Context;
 
 type Result<T = ()> = std::result::Result<T, Box<dyn std::error::Error>>;
 
 const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Bgra8UnormSrgb;
+const ACCUM_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+// the two ping-pong accumulation targets and the bind groups that sample them.
+struct Accum {
+  view: [wgpu::TextureView; 2],
+  bind: [wgpu::BindGroup; 2],
+}
+
+impl Accum {
+  fn new(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    sampler: &wgpu::Sampler,
+    size: PhysicalSize<u32>,
+  ) -> Self {
+    let make = |_| {
+      let tex = device.create_texture(&wgpu::TextureDescriptor {
+        size: wgpu::Extent3d {
+          width: size.width.max(1),
+          height: size.height.max(1),
+          depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: ACCUM_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+        label: None,
+      });
+      tex.create_view(&wgpu::TextureViewDescriptor::default())
+    };
+    let view = [make(0), make(1)];
+    let bind = [0, 1].map(|i| {
+      device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &[
+          wgpu::BindGroupEntry {
+            binding: 0,
+            resource: wgpu::BindingResource::TextureView(&view[i]),
+          },
+          wgpu::BindGroupEntry {
+            binding: 1,
+            resource: wgpu::BindingResource::Sampler(sampler),
+          },
+        ],
+        label: None,
+      })
+    });
+    Self { view, bind }
+  }
+}
 
 fn main() -> Result {
   env_logger::builder()
@@ -24,6 +82,7 @@ fn main() -> Result {
   std::panic::set_hook(Box::new(|i| log::error!("{}", i)));
   let event_loop = EventLoop::new()?;
   let window = WindowBuilder::new().build(&event_loop)?;
+  window.set_ime_allowed(true);
 
   let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
   let surface = unsafe { instance.create_surface(&window)? };
@@ -46,10 +105,41 @@ fn main() -> Result {
   ))?;
   resize(&surface, &device, window.inner_size());
 
+  let mut scene = Scene::new(&device);
+  let grey = scene.material(glam::Vec3::splat(0.8), 0.0);
+  let light = scene.material(glam::Vec3::ONE, 4.0);
+  scene
+    .sphere(glam::Vec3::new(0.0, 0.0, 0.0), 1.0, grey)
+    .sphere(glam::Vec3::new(0.0, -101.0, 0.0), 100.0, grey)
+    .sphere(glam::Vec3::new(2.0, 2.0, 1.0), 0.5, light);
+  scene.upload(&queue);
+
+  let accum_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+    entries: &[
+      wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+          multisampled: false,
+          view_dimension: wgpu::TextureViewDimension::D2,
+          sample_type: wgpu::TextureSampleType::Float { filterable: false },
+        },
+        count: None,
+      },
+      wgpu::BindGroupLayoutEntry {
+        binding: 1,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
+        count: None,
+      },
+    ],
+    label: None,
+  });
+
   let rt_shader = device.create_shader_module(wgpu::include_spirv!(env!("rt.spv")));
   let rt_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
     label: None,
-    bind_group_layouts: &[],
+    bind_group_layouts: &[&scene.layout, &accum_layout],
     push_constant_ranges: &[wgpu::PushConstantRange {
       stages: wgpu::ShaderStages::FRAGMENT,
       range: 0..mem::size_of::<Consts>() as _,
@@ -65,6 +155,33 @@ fn main() -> Result {
     fragment: Some(wgpu::FragmentState {
       module: &rt_shader,
       entry_point: "main_f",
+      targets: &[Some(wgpu::ColorTargetState {
+        format: ACCUM_FORMAT,
+        blend: None,
+        write_mask: wgpu::ColorWrites::ALL,
+      })],
+    }),
+    primitive: wgpu::PrimitiveState::default(),
+    depth_stencil: None,
+    multisample: wgpu::MultisampleState::default(),
+    multiview: None,
+    label: None,
+  });
+  // tonemaps the accumulation texture into the surface.
+  let blit_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+    layout: Some(&device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+      label: None,
+      bind_group_layouts: &[&accum_layout],
+      push_constant_ranges: &[],
+    })),
+    vertex: wgpu::VertexState {
+      module: &rt_shader,
+      entry_point: "main_v",
+      buffers: &[],
+    },
+    fragment: Some(wgpu::FragmentState {
+      module: &rt_shader,
+      entry_point: "main_blit",
       targets: &[Some(wgpu::ColorTargetState {
         format: FORMAT,
         blend: None,
@@ -144,6 +261,19 @@ fn main() -> Result {
     ..Default::default()
   });
 
+  let accum_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+    mag_filter: wgpu::FilterMode::Nearest,
+    min_filter: wgpu::FilterMode::Nearest,
+    ..Default::default()
+  });
+  let mut accum = Accum::new(&device, &accum_layout, &accum_sampler, window.inner_size());
+  let mut cur = 0usize;
+  let mut frame = 0u32;
+
+  let mut camera = Camera::new();
+  let mut last_frame = Instant::now();
+  let mut scene_name = String::from("floppa");
+
   let mut ctx = Context::new();
   ctx.fonts().add_font(include_bytes!("roboto.ttf"), 18.0)?;
   let size = wgpu::Extent3d {
@@ -193,10 +323,14 @@ fn main() -> Result {
   });
 
   event_loop.run(move |event, elwt| {
-    handle_ui_event(&mut ctx, &event);
+    handle_ui_event(&mut ctx, &mut camera, &event);
     match event {
       Event::WindowEvent { event, .. } => match event {
-        WindowEvent::Resized(size) => resize(&surface, &device, size),
+        WindowEvent::Resized(size) => {
+          resize(&surface, &device, size);
+          accum = Accum::new(&device, &accum_layout, &accum_sampler, size);
+          frame = 0;
+        }
         WindowEvent::CloseRequested => elwt.exit(),
         WindowEvent::RedrawRequested => {
           let surface = surface.get_current_texture().unwrap();
@@ -207,10 +341,13 @@ fn main() -> Result {
             device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
 
           let mut ui = ctx.begin_frame();
-          ui.text("hello floppa");
-          if ui.button("button") {
-            log::info!("pressed");
-          }
+          ui.window("debug", |ui| {
+            ui.text("hello floppa");
+            ui.text_edit(&mut scene_name);
+            if ui.button("button") {
+              log::info!("pressed {}", scene_name);
+            }
+          });
           let out = ctx.end_frame();
 
           let vtx_buf = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -224,9 +361,31 @@ fn main() -> Result {
             label: None,
           });
 
+          let now = Instant::now();
+          camera.update((now - last_frame).as_secs_f32());
+          last_frame = now;
+          // camera motion invalidates the history, so restart the average.
+          if camera.dirty {
+            frame = 0;
+            camera.dirty = false;
+          }
+          let consts = Consts {
+            screen_size: Vec2::new(surface.texture.width() as _, surface.texture.height() as _),
+            cam_origin: camera.eye,
+            cam_forward: camera.forward,
+            cam_right: camera.right,
+            cam_up: camera.up,
+            fov: camera.fov,
+            // the shader blends with `1.0/frame`, so the count must start at 1
+            // on the first sample after a reset to avoid a divide-by-zero.
+            frame: frame + 1,
+            seed: frame.wrapping_mul(0x9e3779b9),
+          };
+
+          // trace one sample and blend it with the previous history into `cur`.
           let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-              view: &surface_view,
+              view: &accum.view[cur],
               resolve_target: None,
               ops: wgpu::Operations {
                 load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
@@ -236,13 +395,45 @@ fn main() -> Result {
             depth_stencil_attachment: None,
             label: None,
           });
-          let consts = Consts {
-            screen_size: Vec2::new(surface.texture.width() as _, surface.texture.height() as _),
-          };
           render_pass.set_pipeline(&rt_pipeline);
+          render_pass.set_bind_group(0, &scene.bind_group, &[]);
+          render_pass.set_bind_group(1, &accum.bind[1 - cur], &[]);
           render_pass.set_push_constants(wgpu::ShaderStages::FRAGMENT, 0, cast(&consts));
           render_pass.draw(0..3, 0..1);
+          drop(render_pass);
+
+          // tonemap the freshly written history into the surface.
+          let mut blit_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+              view: &surface_view,
+              resolve_target: None,
+              ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                store: true,
+              },
+            })],
+            depth_stencil_attachment: None,
+            label: None,
+          });
+          blit_pass.set_pipeline(&blit_pipeline);
+          blit_pass.set_bind_group(0, &accum.bind[cur], &[]);
+          blit_pass.draw(0..3, 0..1);
+          drop(blit_pass);
+          cur = 1 - cur;
+          frame += 1;
 
+          let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+              view: &surface_view,
+              resolve_target: None,
+              ops: wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: true,
+              },
+            })],
+            depth_stencil_attachment: None,
+            label: None,
+          });
           render_pass.set_pipeline(&ui_pipeline);
           render_pass.set_push_constants(wgpu::ShaderStages::VERTEX, 0, cast(&consts));
           render_pass.set_bind_group(0, &tex_bind_group, &[]);
@@ -278,20 +469,63 @@ fn resize(surface: &wgpu::Surface, device: &wgpu::Device, size: PhysicalSize<u32
   );
 }
 
-fn handle_ui_event<T>(ctx: &mut Context, event: &Event<T>) {
+fn handle_ui_event<T>(ctx: &mut Context, camera: &mut Camera, event: &Event<T>) {
+  // the UI gets first refusal on input: when the pointer is over a window (or a
+  // text_edit holds keyboard focus) the camera must not also consume the event,
+  // otherwise dragging a title bar rotates the view and typing drives the fly cam.
+  let ui_pointer = ctx.wants_pointer();
+  let ui_keyboard = ctx.wants_keyboard();
   let input = ctx.input();
   match event {
     Event::WindowEvent { event, .. } => match event {
       WindowEvent::CursorMoved { position, .. } => {
         input.cursor_pos = Vec2::new(position.x as _, position.y as _);
+        camera.cursor(input.cursor_pos);
       }
       WindowEvent::MouseInput { button, state, .. } => {
-        input.mouse_buttons[match button {
+        let i = match button {
           MouseButton::Left => 0,
           MouseButton::Middle => 2,
           MouseButton::Right => 3,
           _ => return,
-        }] = *state == ElementState::Pressed;
+        };
+        let pressed = *state == ElementState::Pressed;
+        input.mouse_buttons[i] = pressed;
+        // only start a camera drag when the press didn't land on the UI; always
+        // forward the release so a drag in progress can't get stuck.
+        if i == 0 && !(pressed && ui_pointer) {
+          camera.drag(pressed, input.cursor_pos);
+        }
+      }
+      WindowEvent::MouseWheel { delta, .. } => {
+        let y = match delta {
+          MouseScrollDelta::LineDelta(_, y) => *y,
+          MouseScrollDelta::PixelDelta(p) => p.y as f32 / 120.0,
+        };
+        if !ui_pointer {
+          camera.scroll(y);
+        }
+      }
+      WindowEvent::KeyboardInput { event, .. } => {
+        if let PhysicalKey::Code(code) = event.physical_key {
+          // don't let WASD/Tab drive the fly camera while a text_edit is focused;
+          // releases still go through so a key held before focus can't get stuck.
+          if !ui_keyboard || event.state == ElementState::Released {
+            camera.key(code, event.state == ElementState::Pressed);
+          }
+        }
+        if event.state == ElementState::Pressed {
+          input.keys.push(event.logical_key.clone());
+          if let Some(text) = &event.text {
+            input.text.push_str(text);
+          }
+        }
+      }
+      WindowEvent::Ime(winit::event::Ime::Commit(text)) => {
+        input.text.push_str(text);
+      }
+      WindowEvent::ModifiersChanged(mods) => {
+        input.modifiers = mods.state();
       }
       _ => {}
     },
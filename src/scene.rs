@@ -0,0 +1,131 @@
+use std::mem;
+use wgpu::util::DeviceExt;
+use glam::Vec3;
+
+// std430-compatible mirror of the `Sphere` the fragment shader reads.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct Sphere {
+  pub center: Vec3,
+  pub radius: f32,
+  pub material: u32,
+  pub _pad: [u32; 3],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct Material {
+  pub albedo: Vec3,
+  pub emissive: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Counts {
+  spheres: u32,
+  materials: u32,
+  _pad: [u32; 2],
+}
+
+// CPU-side scene description, uploaded into a read-only storage buffer.
+pub struct Scene {
+  spheres: Vec<Sphere>,
+  materials: Vec<Material>,
+  sphere_buf: wgpu::Buffer,
+  material_buf: wgpu::Buffer,
+  counts_buf: wgpu::Buffer,
+  pub layout: wgpu::BindGroupLayout,
+  pub bind_group: wgpu::BindGroup,
+}
+
+const CAP: u64 = 256;
+
+impl Scene {
+  pub fn new(device: &wgpu::Device) -> Self {
+    let storage = |size| wgpu::BufferDescriptor {
+      size,
+      usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+      label: None,
+    };
+    let sphere_buf = device.create_buffer(&storage(CAP * mem::size_of::<Sphere>() as u64));
+    let material_buf = device.create_buffer(&storage(CAP * mem::size_of::<Material>() as u64));
+    let counts_buf = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      contents: cast(&Counts { spheres: 0, materials: 0, _pad: [0; 2] }),
+      usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+      label: None,
+    });
+
+    let entry = |binding, ty| wgpu::BindGroupLayoutEntry {
+      binding,
+      visibility: wgpu::ShaderStages::FRAGMENT,
+      ty,
+      count: None,
+    };
+    let storage_ty = wgpu::BindingType::Buffer {
+      ty: wgpu::BufferBindingType::Storage { read_only: true },
+      has_dynamic_offset: false,
+      min_binding_size: None,
+    };
+    let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+      entries: &[
+        entry(0, storage_ty),
+        entry(1, storage_ty),
+        entry(2, wgpu::BindingType::Buffer {
+          ty: wgpu::BufferBindingType::Uniform,
+          has_dynamic_offset: false,
+          min_binding_size: None,
+        }),
+      ],
+      label: None,
+    });
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+      layout: &layout,
+      entries: &[
+        wgpu::BindGroupEntry { binding: 0, resource: sphere_buf.as_entire_binding() },
+        wgpu::BindGroupEntry { binding: 1, resource: material_buf.as_entire_binding() },
+        wgpu::BindGroupEntry { binding: 2, resource: counts_buf.as_entire_binding() },
+      ],
+      label: None,
+    });
+
+    Self {
+      spheres: Vec::new(),
+      materials: Vec::new(),
+      sphere_buf,
+      material_buf,
+      counts_buf,
+      layout,
+      bind_group,
+    }
+  }
+
+  pub fn material(&mut self, albedo: Vec3, emissive: f32) -> u32 {
+    self.materials.push(Material { albedo, emissive });
+    self.materials.len() as u32 - 1
+  }
+
+  pub fn sphere(&mut self, center: Vec3, radius: f32, material: u32) -> &mut Self {
+    self.spheres.push(Sphere { center, radius, material, _pad: [0; 3] });
+    self
+  }
+
+  // re-upload the scene; call after pushing geometry or when it changes.
+  pub fn upload(&self, queue: &wgpu::Queue) {
+    queue.write_buffer(&self.sphere_buf, 0, cast_slice(&self.spheres));
+    queue.write_buffer(&self.material_buf, 0, cast_slice(&self.materials));
+    queue.write_buffer(&self.counts_buf, 0, cast(&Counts {
+      spheres: self.spheres.len() as u32,
+      materials: self.materials.len() as u32,
+      _pad: [0; 2],
+    }));
+  }
+}
+
+fn cast_slice<T>(t: &[T]) -> &[u8] {
+  unsafe { std::slice::from_raw_parts(t.as_ptr() as _, mem::size_of_val(t)) }
+}
+
+fn cast<T>(t: &T) -> &[u8] {
+  cast_slice(std::slice::from_ref(t))
+}
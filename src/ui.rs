@@ -0,0 +1,452 @@
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use glam::{Vec2, Vec3};
+use winit::keyboard::{Key, NamedKey, ModifiersState};
+use shared::Vertex;
+
+type Result<T = ()> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+// overlay palette, kept together so every window looks the same.
+const PANEL: Vec3 = Vec3::new(0.12, 0.12, 0.14);
+const TITLE: Vec3 = Vec3::new(0.20, 0.22, 0.30);
+const HOVER: Vec3 = Vec3::new(0.28, 0.30, 0.38);
+const BUTTON: Vec3 = Vec3::new(0.24, 0.26, 0.32);
+const TEXT: Vec3 = Vec3::ONE;
+const PAD: f32 = 4.0;
+const TITLE_H: f32 = 22.0;
+const GRIP: f32 = 12.0;
+
+// a rasterised glyph packed into the font atlas.
+#[derive(Clone, Copy)]
+struct Glyph {
+  uv_min: Vec2,
+  uv_max: Vec2,
+  size: Vec2,
+  offset: Vec2,
+  advance: f32,
+}
+
+// the font atlas: every printable ascii glyph rasterised once into an rgba8 texture.
+pub struct Fonts {
+  px: f32,
+  line: f32,
+  ascent: f32,
+  atlas: Vec<u8>,
+  width: u32,
+  height: u32,
+  white: Vec2,
+  glyphs: HashMap<char, Glyph>,
+}
+
+impl Fonts {
+  fn new() -> Self {
+    Self {
+      px: 0.0,
+      line: 0.0,
+      ascent: 0.0,
+      atlas: Vec::new(),
+      width: 0,
+      height: 0,
+      white: Vec2::ZERO,
+      glyphs: HashMap::new(),
+    }
+  }
+
+  // rasterise the printable ascii range into a single-row atlas, preceded by a
+  // white block the solid-colour quads sample from.
+  pub fn add_font(&mut self, bytes: &[u8], px: f32) -> Result {
+    let font = fontdue::Font::from_bytes(bytes, fontdue::FontSettings { scale: px, ..Default::default() })
+      .map_err(|e| e.to_owned())?;
+    let metrics = font.horizontal_line_metrics(px).unwrap();
+    self.px = px;
+    self.ascent = metrics.ascent;
+    self.line = metrics.new_line_size.max(px);
+
+    let rasters: Vec<_> = (' '..='~').map(|c| (c, font.rasterize(c, px))).collect();
+    let height = rasters.iter().map(|(_, (m, _))| m.height).max().unwrap_or(1).max(2);
+    let white = 2;
+    let width = white + rasters.iter().map(|(_, (m, _))| m.width + 1).sum::<usize>();
+
+    let mut atlas = vec![0u8; width * height * 4];
+    for y in 0..height {
+      for x in 0..white {
+        let i = (y * width + x) * 4;
+        atlas[i..i + 4].copy_from_slice(&[255, 255, 255, 255]);
+      }
+    }
+    self.white = Vec2::new(0.5 / width as f32, 0.5 / height as f32);
+
+    let mut pen = white;
+    for (c, (m, bm)) in &rasters {
+      for y in 0..m.height {
+        for x in 0..m.width {
+          let i = (y * width + pen + x) * 4;
+          atlas[i..i + 4].copy_from_slice(&[255, 255, 255, bm[y * m.width + x]]);
+        }
+      }
+      self.glyphs.insert(*c, Glyph {
+        uv_min: Vec2::new(pen as f32 / width as f32, 0.0),
+        uv_max: Vec2::new((pen + m.width) as f32 / width as f32, m.height as f32 / height as f32),
+        size: Vec2::new(m.width as f32, m.height as f32),
+        offset: Vec2::new(m.xmin as f32, m.ymin as f32),
+        advance: m.advance_width,
+      });
+      pen += m.width + 1;
+    }
+
+    self.atlas = atlas;
+    self.width = width as u32;
+    self.height = height as u32;
+    Ok(())
+  }
+
+  pub fn size(&self) -> (u32, u32) {
+    (self.width, self.height)
+  }
+
+  pub fn build_tex(&self) -> Vec<u8> {
+    self.atlas.clone()
+  }
+
+  fn measure(&self, s: &str) -> f32 {
+    s.chars().filter_map(|c| self.glyphs.get(&c)).map(|g| g.advance).sum()
+  }
+}
+
+// pointer and keyboard state fed in from `handle_ui_event` before each frame.
+// `keys`/`text` are a per-frame queue drained by the focused widget and cleared
+// in `end_frame`.
+pub struct Input {
+  pub cursor_pos: Vec2,
+  pub mouse_buttons: [bool; 4],
+  pub keys: Vec<Key>,
+  pub text: String,
+  pub modifiers: ModifiersState,
+}
+
+impl Input {
+  fn new() -> Self {
+    Self {
+      cursor_pos: Vec2::ZERO,
+      mouse_buttons: [false; 4],
+      keys: Vec::new(),
+      text: String::new(),
+      modifiers: ModifiersState::empty(),
+    }
+  }
+}
+
+// the geometry produced for one frame, consumed by the ui pipeline.
+#[derive(Default)]
+pub struct Output {
+  pub vtx_buf: Vec<Vertex>,
+  pub idx_buf: Vec<u32>,
+}
+
+#[derive(Clone, Copy)]
+struct Rect {
+  min: Vec2,
+  max: Vec2,
+}
+
+impl Rect {
+  fn contains(&self, p: Vec2) -> bool {
+    p.x >= self.min.x && p.x <= self.max.x && p.y >= self.min.y && p.y <= self.max.y
+  }
+}
+
+// persisted per-window position/size, keyed by title.
+#[derive(Clone, Copy)]
+struct WindowState {
+  pos: Vec2,
+  size: Vec2,
+}
+
+enum DragKind {
+  Move,
+  Resize,
+}
+
+// an in-progress title-bar drag or corner resize.
+struct Drag {
+  title: String,
+  kind: DragKind,
+  grab: Vec2,
+}
+
+pub struct Context {
+  fonts: Fonts,
+  input: Input,
+  windows: HashMap<String, WindowState>,
+  out: Output,
+  active: Option<Drag>,
+  prev_mouse0: bool,
+  hot: bool,
+  // which text_edit (by widget id) owns the keyboard, and its caret offset.
+  focus: Option<u64>,
+  caret: usize,
+  frame: u64,
+}
+
+impl Context {
+  pub fn new() -> Self {
+    Self {
+      fonts: Fonts::new(),
+      input: Input::new(),
+      windows: HashMap::new(),
+      out: Output::default(),
+      active: None,
+      prev_mouse0: false,
+      hot: false,
+      focus: None,
+      caret: 0,
+      frame: 0,
+    }
+  }
+
+  pub fn fonts(&mut self) -> &mut Fonts {
+    &mut self.fonts
+  }
+
+  pub fn input(&mut self) -> &mut Input {
+    &mut self.input
+  }
+
+  // true while the pointer is over a window or a drag is in progress, so the
+  // caller can stop the camera from also consuming the event.
+  pub fn wants_pointer(&self) -> bool {
+    self.hot || self.active.is_some()
+  }
+
+  // true while a text_edit holds focus, so keystrokes don't also drive the camera.
+  pub fn wants_keyboard(&self) -> bool {
+    self.focus.is_some()
+  }
+
+  pub fn begin_frame(&mut self) -> Ui {
+    self.out.vtx_buf.clear();
+    self.out.idx_buf.clear();
+    self.hot = false;
+    self.frame += 1;
+    if !self.input.mouse_buttons[0] {
+      self.active = None;
+    }
+    Ui { ctx: self, region: None, seq: 0 }
+  }
+
+  pub fn end_frame(&mut self) -> &Output {
+    self.prev_mouse0 = self.input.mouse_buttons[0];
+    self.input.keys.clear();
+    self.input.text.clear();
+    &self.out
+  }
+
+  // a flat, texture-sampled triangle pair.
+  fn rect(&mut self, r: Rect, uv0: Vec2, uv1: Vec2, col: Vec3) {
+    let base = self.out.vtx_buf.len() as u32;
+    let v = |pos, uv| Vertex { pos, uv, col };
+    self.out.vtx_buf.push(v(r.min, uv0));
+    self.out.vtx_buf.push(v(Vec2::new(r.max.x, r.min.y), Vec2::new(uv1.x, uv0.y)));
+    self.out.vtx_buf.push(v(r.max, uv1));
+    self.out.vtx_buf.push(v(Vec2::new(r.min.x, r.max.y), Vec2::new(uv0.x, uv1.y)));
+    self.out.idx_buf.extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+  }
+
+  fn quad(&mut self, r: Rect, col: Vec3) {
+    let w = self.fonts.white;
+    self.rect(r, w, w, col);
+  }
+
+  // lay out a string from `pos`, culling glyphs that fall outside `clip`.
+  fn text_at(&mut self, s: &str, pos: Vec2, col: Vec3, clip: Rect) -> f32 {
+    let mut x = pos.x;
+    let baseline = pos.y + self.fonts.ascent;
+    for c in s.chars() {
+      if let Some(g) = self.fonts.glyphs.get(&c).copied() {
+        let top = baseline - (g.offset.y + g.size.y);
+        let min = Vec2::new(x + g.offset.x, top);
+        let r = Rect { min, max: min + g.size };
+        if clip.contains(r.min) && clip.contains(r.max) {
+          self.rect(r, g.uv_min, g.uv_max, col);
+        }
+        x += g.advance;
+      }
+    }
+    x - pos.x
+  }
+}
+
+// a frame-scoped builder; `window` pushes a layout region child widgets draw into.
+pub struct Ui<'a> {
+  ctx: &'a mut Context,
+  region: Option<Region>,
+  seq: u64,
+}
+
+struct Region {
+  rect: Rect,
+  cursor: Vec2,
+  // hash of the owning window's title; mixed with the widget's index in the
+  // frame (counted across all windows) to give each widget a stable id across
+  // frames.
+  id: u64,
+}
+
+impl Ui<'_> {
+  // open a draggable, resizable window whose position/size persist across frames.
+  pub fn window(&mut self, title: &str, f: impl FnOnce(&mut Ui)) {
+    let mut st = *self.ctx.windows.get(title).unwrap_or(&WindowState {
+      pos: Vec2::new(16.0, 16.0),
+      size: Vec2::new(220.0, 160.0),
+    });
+    let cursor = self.ctx.input.cursor_pos;
+    let pressed = self.ctx.input.mouse_buttons[0] && !self.ctx.prev_mouse0;
+
+    let title_bar = Rect { min: st.pos, max: st.pos + Vec2::new(st.size.x, TITLE_H) };
+    let grip = Rect { min: st.pos + st.size - Vec2::splat(GRIP), max: st.pos + st.size };
+    // grab the title bar to move or the corner grip to resize.
+    if pressed && self.ctx.active.is_none() {
+      if grip.contains(cursor) {
+        self.ctx.active = Some(Drag { title: title.into(), kind: DragKind::Resize, grab: st.pos + st.size - cursor });
+      } else if title_bar.contains(cursor) {
+        self.ctx.active = Some(Drag { title: title.into(), kind: DragKind::Move, grab: cursor - st.pos });
+      }
+    }
+    if let Some(d) = &self.ctx.active {
+      if d.title == title {
+        match d.kind {
+          DragKind::Move => st.pos = cursor - d.grab,
+          DragKind::Resize => {
+            st.size = (cursor + d.grab - st.pos).max(Vec2::new(80.0, TITLE_H + GRIP));
+          }
+        }
+      }
+    }
+
+    let win = Rect { min: st.pos, max: st.pos + st.size };
+    if win.contains(cursor) {
+      self.ctx.hot = true;
+    }
+    self.ctx.quad(win, PANEL);
+    self.ctx.quad(Rect { min: st.pos, max: st.pos + Vec2::new(st.size.x, TITLE_H) }, TITLE);
+    self.ctx.text_at(title, st.pos + Vec2::new(PAD, (TITLE_H - self.ctx.fonts.px) * 0.5), TEXT, win);
+    self.ctx.quad(grip, BUTTON);
+
+    let content = Rect {
+      min: st.pos + Vec2::new(PAD, TITLE_H + PAD),
+      max: st.pos + st.size - Vec2::splat(PAD),
+    };
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    title.hash(&mut hasher);
+    self.region = Some(Region { rect: content, cursor: content.min, id: hasher.finish() });
+    f(self);
+    self.region = None;
+    self.ctx.windows.insert(title.into(), st);
+  }
+
+  pub fn text(&mut self, s: &str) {
+    let Some((pos, clip)) = self.region.as_ref().map(|r| (r.cursor, r.rect)) else {
+      return;
+    };
+    let line = self.ctx.fonts.line;
+    self.ctx.text_at(s, pos, TEXT, clip);
+    self.advance(line);
+  }
+
+  pub fn button(&mut self, label: &str) -> bool {
+    let Some((pos, clip)) = self.region.as_ref().map(|r| (r.cursor, r.rect)) else {
+      return false;
+    };
+    let h = self.ctx.fonts.line;
+    let rect = Rect { min: pos, max: pos + Vec2::new(self.ctx.fonts.measure(label) + PAD * 2.0, h) };
+    let cursor = self.ctx.input.cursor_pos;
+    let hovered = rect.contains(cursor) && clip.contains(cursor);
+    let clicked = hovered && self.ctx.input.mouse_buttons[0] && !self.ctx.prev_mouse0;
+    self.ctx.quad(rect, if hovered { HOVER } else { BUTTON });
+    self.ctx.text_at(label, pos + Vec2::new(PAD, 0.0), TEXT, clip);
+    self.advance(h + PAD);
+    clicked
+  }
+
+  // an editable single-line text field. Clicking focuses it; while focused it
+  // consumes the queued typed text and Backspace/arrow keys and draws a caret.
+  pub fn text_edit(&mut self, value: &mut String) {
+    let seq = self.next_seq();
+    let Some((pos, clip, id)) = self.region.as_ref().map(|r| (r.cursor, r.rect, r.id ^ seq)) else {
+      return;
+    };
+    let h = self.ctx.fonts.line;
+    let rect = Rect { min: pos, max: Vec2::new(clip.max.x, pos.y + h) };
+    let cursor = self.ctx.input.cursor_pos;
+    let hovered = rect.contains(cursor) && clip.contains(cursor);
+
+    if self.ctx.input.mouse_buttons[0] && !self.ctx.prev_mouse0 {
+      if hovered {
+        self.ctx.focus = Some(id);
+        self.ctx.caret = value.len();
+      } else if self.ctx.focus == Some(id) {
+        self.ctx.focus = None;
+      }
+    }
+
+    let focused = self.ctx.focus == Some(id);
+    if focused {
+      self.edit(value);
+    }
+
+    let bg = if focused { HOVER } else { BUTTON };
+    self.ctx.quad(rect, bg);
+    self.ctx.text_at(value, pos + Vec2::new(PAD, 0.0), TEXT, clip);
+    // blink the caret while focused: shown for 30 frames, hidden for 30.
+    if focused && (self.ctx.frame / 30) % 2 == 0 {
+      let caret = self.ctx.caret.min(value.len());
+      let x = pos.x + PAD + self.ctx.fonts.measure(&value[..caret]);
+      self.ctx.quad(Rect { min: Vec2::new(x, pos.y + 2.0), max: Vec2::new(x + 1.0, pos.y + h - 2.0) }, TEXT);
+    }
+    self.advance(h + PAD);
+  }
+
+  // apply this frame's queued keystrokes to the focused string.
+  fn edit(&mut self, value: &mut String) {
+    let mut caret = self.ctx.caret.min(value.len());
+    for key in &self.ctx.input.keys {
+      match key {
+        Key::Named(NamedKey::Backspace) if caret > 0 => {
+          let prev = value[..caret].chars().next_back().map(|c| c.len_utf8()).unwrap_or(0);
+          value.replace_range(caret - prev..caret, "");
+          caret -= prev;
+        }
+        Key::Named(NamedKey::Delete) if caret < value.len() => {
+          let next = value[caret..].chars().next().map(|c| c.len_utf8()).unwrap_or(0);
+          value.replace_range(caret..caret + next, "");
+        }
+        Key::Named(NamedKey::ArrowLeft) if caret > 0 => {
+          caret -= value[..caret].chars().next_back().map(|c| c.len_utf8()).unwrap_or(0);
+        }
+        Key::Named(NamedKey::ArrowRight) if caret < value.len() => {
+          caret += value[caret..].chars().next().map(|c| c.len_utf8()).unwrap_or(0);
+        }
+        Key::Named(NamedKey::Home) => caret = 0,
+        Key::Named(NamedKey::End) => caret = value.len(),
+        _ => {}
+      }
+    }
+    // insert typed characters (control chars are filtered out of the queue).
+    for c in self.ctx.input.text.chars().filter(|c| !c.is_control()) {
+      value.insert(caret, c);
+      caret += c.len_utf8();
+    }
+    self.ctx.caret = caret;
+  }
+
+  fn next_seq(&mut self) -> u64 {
+    self.seq = self.seq.wrapping_add(1);
+    self.seq.wrapping_mul(0x9e3779b97f4a7c15)
+  }
+
+  fn advance(&mut self, dy: f32) {
+    if let Some(r) = self.region.as_mut() {
+      r.cursor.y += dy;
+    }
+  }
+}